@@ -1,4 +1,4 @@
-//! A helper widget, useful for instantiating a sequence of widgets in a vertical list.
+//! A helper widget, useful for instantiating a sequence of widgets in a list.
 
 use {
     color,
@@ -6,6 +6,7 @@ use {
     Colorable,
     NodeIndex,
     Positionable,
+    Rect,
     Scalar,
     Sizeable,
     Widget,
@@ -13,10 +14,14 @@ use {
     UiCell,
 };
 use graph;
+use input::keyboard::Key;
 use std;
+use std::cell::RefCell;
+use std::rc::Rc;
 use widget;
 
-/// A helper widget, useful for instantiating a sequence of widgets in a vertical list.
+/// A helper widget, useful for instantiating a sequence of widgets in a vertical or horizontal
+/// list.
 ///
 /// The `List` widget simplifies this process by:
 ///
@@ -29,9 +34,75 @@ use widget;
 pub struct List {
     common: widget::CommonBuilder,
     style: Style,
-    item_h: Scalar,
+    item_sizes: ItemSizes,
     num_items: u32,
     item_instantiation: ItemInstantiation,
+    direction: Direction,
+    selected: Option<usize>,
+    select_on_click: bool,
+}
+
+/// The axis along which a `List`'s items flow.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    /// Items are stacked top-to-bottom and the `List` scrolls vertically.
+    Vertical,
+    /// Items are stacked left-to-right and the `List` scrolls horizontally.
+    Horizontal,
+}
+
+impl Direction {
+    /// The length of the given `Rect` along this `Direction`'s axis, i.e. the dimension in which
+    /// items are laid out and scrolled.
+    fn primary_len(&self, rect: &Rect) -> Scalar {
+        match *self {
+            Direction::Vertical => rect.h(),
+            Direction::Horizontal => rect.w(),
+        }
+    }
+
+    /// The length of the given `Rect` along the axis perpendicular to this `Direction`'s axis.
+    fn cross_len(&self, rect: &Rect) -> Scalar {
+        match *self {
+            Direction::Vertical => rect.w(),
+            Direction::Horizontal => rect.h(),
+        }
+    }
+
+    /// The position of the leading edge of the given `Rect` along this `Direction`'s axis, i.e.
+    /// the edge from which `offsets` are measured (the top for `Vertical`, the left for
+    /// `Horizontal`).
+    fn leading_edge(&self, rect: &Rect) -> Scalar {
+        match *self {
+            Direction::Vertical => rect.top(),
+            Direction::Horizontal => rect.left(),
+        }
+    }
+
+    /// The distance by which the `scroll_trigger` `Rect` has been scrolled past the leading edge
+    /// of the visible `rect`.
+    fn hidden_len(&self, scroll_trigger: &Rect, rect: &Rect) -> Scalar {
+        match *self {
+            Direction::Vertical => scroll_trigger.top() - rect.top(),
+            Direction::Horizontal => rect.left() - scroll_trigger.left(),
+        }
+    }
+}
+
+/// Describes how the size of each `Item` along the `List`'s primary axis is determined.
+#[derive(Clone)]
+enum ItemSizes {
+    /// Every item shares the same size.
+    Uniform(Scalar),
+    /// The size of each item is retrieved via a function of its index.
+    PerItem(Rc<Fn(usize) -> Scalar>),
+    /// The size of each item is not known up-front. Unmeasured items fall back to `estimate`
+    /// until their actual size has been read back from the `Ui` after being set, at which point
+    /// it is cached in `measured` (shared with the `List`'s persistent `State`).
+    Measured {
+        estimate: Scalar,
+        measured: Rc<RefCell<Vec<Option<Scalar>>>>,
+    },
 }
 
 widget_style! {
@@ -43,6 +114,11 @@ widget_style! {
         - scrollbar_color: Color { theme.border_color }
         /// The location of the `List`'s scrollbar.
         - scrollbar_position: Option<ScrollbarPosition> { None }
+        /// The gap between the items and the scrollbar's track.
+        ///
+        /// A value greater than `0.0` produces an embedded scrollbar that floats with a margin
+        /// rather than sitting flush against the edge of the items.
+        - scrollbar_offset: Scalar { 0.0 }
     }
 }
 
@@ -52,10 +128,15 @@ pub struct State {
     scroll_trigger_idx: widget::IndexSlot,
     item_indices: Vec<NodeIndex>,
     scrollbar_idx: widget::IndexSlot,
+    selected: Option<usize>,
+    /// Cache of each item's last-measured size, used by `List::measured`. Shared via `Rc` with
+    /// the `ItemSizes::Measured` used during `update`, `Items::next` and `Item::set` so that
+    /// newly measured sizes can be written back without waiting for the next frame's `update`.
+    measured: Rc<RefCell<Vec<Option<Scalar>>>>,
 }
 
 /// The data necessary for instantiating a single item within a `List`.
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Item {
     /// The index of the item within the list.
     pub i: usize,
@@ -67,10 +148,16 @@ pub struct Item {
     pub w: Scalar,
     /// The height of the item.
     pub h: Scalar,
+    /// Whether or not this item is currently selected within the `List`.
+    pub is_selected: bool,
+    /// The axis along which this item flows relative to the previous item.
+    direction: Direction,
+    /// The shared measured-size cache to write back into if this `List` is in `measured` mode.
+    measured: Option<Rc<RefCell<Vec<Option<Scalar>>>>>,
     /// The index of the `scroll_trigger` rectangle, upon which this widget will be placed.
     scroll_trigger_idx: NodeIndex,
-    /// The distance between the top of the first visible item and the top of the `scroll_trigger`
-    /// `Rectangle`. This field is used for positioning the item's widget.
+    /// The distance between the leading edge of the first visible item and the leading edge of
+    /// the `scroll_trigger` `Rectangle`. This field is used for positioning the item's widget.
     first_item_margin: Scalar,
 }
 
@@ -86,18 +173,52 @@ pub enum ItemInstantiation {
 /// If the `List` is scrollable, this describes how th `Scrollbar` should be positioned.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ScrollbarPosition {
-    /// To the right of the items (reduces the item width to fit).
+    /// To the right (or below, for horizontal lists) of the items (reduces the cross-axis size to
+    /// fit).
     NextTo,
-    /// On top of the right edge of the items with auto_hide activated.
+    /// To the left (or above, for horizontal lists) of the items (reduces the cross-axis size to
+    /// fit).
+    NextToLeft,
+    /// On top of the far edge (right, or bottom for horizontal lists) of the items with
+    /// auto_hide activated.
     OnTop,
+    /// On top of the near edge (left, or top for horizontal lists) of the items with auto_hide
+    /// activated.
+    OnTopLeft,
+}
+
+impl ScrollbarPosition {
+    /// Whether this `ScrollbarPosition` places the scrollbar at the near edge (left, or top for
+    /// horizontal lists) rather than the far edge.
+    fn is_left(&self) -> bool {
+        match *self {
+            ScrollbarPosition::NextToLeft | ScrollbarPosition::OnTopLeft => true,
+            ScrollbarPosition::NextTo | ScrollbarPosition::OnTop => false,
+        }
+    }
+
+    /// Whether this `ScrollbarPosition` should auto-hide and embed on top of the items rather
+    /// than reducing the items' cross-axis size.
+    fn auto_hide(&self) -> bool {
+        match *self {
+            ScrollbarPosition::OnTop | ScrollbarPosition::OnTopLeft => true,
+            ScrollbarPosition::NextTo | ScrollbarPosition::NextToLeft => false,
+        }
+    }
 }
 
 /// A wrapper around a `List`'s `Scrollbar` and its `NodeIndex`.
 pub struct Scrollbar {
-    widget: widget::Scrollbar<widget::scroll::Y>,
+    widget: ScrollbarWidget,
     idx: NodeIndex,
 }
 
+/// The two concrete `Scrollbar` types a `List` might instantiate, depending on its `Direction`.
+enum ScrollbarWidget {
+    X(widget::Scrollbar<widget::scroll::X>),
+    Y(widget::Scrollbar<widget::scroll::Y>),
+}
+
 /// An `Iterator` yielding each `Item` in the list.
 pub struct Items {
     item_indices: std::ops::Range<usize>,
@@ -106,36 +227,113 @@ pub struct Items {
     last_idx: Option<NodeIndex>,
     scroll_trigger_idx: NodeIndex,
     first_item_margin: Scalar,
-    item_w: Scalar,
-    item_h: Scalar,
+    cross_axis_len: Scalar,
+    item_sizes: ItemSizes,
+    direction: Direction,
+    selected: Option<usize>,
+    measured: Option<Rc<RefCell<Vec<Option<Scalar>>>>>,
 }
 
 
 impl List {
 
-    /// Create a List context to be built upon.
-    pub fn new(num_items: u32, item_height: Scalar) -> Self {
+    /// Construct a `List` from its `ItemSizes`, sharing the defaults common to every constructor.
+    fn from_item_sizes(num_items: u32, item_sizes: ItemSizes) -> Self {
         List {
             common: widget::CommonBuilder::new(),
             style: Style::new(),
-            item_h: item_height,
+            item_sizes: item_sizes,
             num_items: num_items,
             item_instantiation: ItemInstantiation::OnlyVisible,
+            direction: Direction::Vertical,
+            selected: None,
+            select_on_click: false,
         }.crop_kids()
     }
 
+    /// Create a List context to be built upon.
+    pub fn new(num_items: u32, item_height: Scalar) -> Self {
+        Self::from_item_sizes(num_items, ItemSizes::Uniform(item_height))
+    }
+
+    /// Create a horizontal `List` whose items flow left-to-right and which scrolls along the `x`
+    /// axis.
+    pub fn horizontal(num_items: u32, item_width: Scalar) -> Self {
+        Self::new(num_items, item_width).flow_right()
+    }
+
+    /// Create a `List` whose items may each have a different size along the primary axis.
+    ///
+    /// `sizes` must contain exactly `num_items` entries, each specifying the height (for a
+    /// vertical `List`) or width (for a horizontal `List`) of the item at that index. This is
+    /// useful for lists of wrapped text, images or any other content whose size is known
+    /// up-front but varies per-item.
+    pub fn with_item_heights(num_items: u32, sizes: Vec<Scalar>) -> Self {
+        assert_eq!(sizes.len(), num_items as usize,
+                   "the given `sizes` had {} entries however `num_items` was {}; they must match",
+                   sizes.len(), num_items);
+        Self::with_item_height_fn(num_items, move |i| sizes[i])
+    }
+
+    /// Create a `List` whose item sizes along the primary axis are determined by calling the
+    /// given function with the index of the item.
+    ///
+    /// This is a more flexible alternative to `with_item_heights` for cases where the sizes are
+    /// computed lazily or stored in some other data structure.
+    pub fn with_item_height_fn<F>(num_items: u32, item_size: F) -> Self
+        where F: Fn(usize) -> Scalar + 'static,
+    {
+        Self::from_item_sizes(num_items, ItemSizes::PerItem(Rc::new(item_size)))
+    }
+
+    /// Create a `List` whose item sizes along the primary axis are not known up-front.
+    ///
+    /// This is useful for lists whose rows can't be measured until they've been laid out, e.g.
+    /// chat messages or log lines. Unmeasured items are sized using `estimated_item_height` until
+    /// their actual size has been read back from the `Ui`, after which the cumulative offsets
+    /// used for the visible-range binary search converge to the true positions over successive
+    /// frames.
+    pub fn measured(num_items: u32, estimated_item_height: Scalar) -> Self {
+        let item_sizes = ItemSizes::Measured {
+            estimate: estimated_item_height,
+            measured: Rc::new(RefCell::new(Vec::new())),
+        };
+        Self::from_item_sizes(num_items, item_sizes)
+    }
+
+    /// Lay the `List`'s items out left-to-right and scroll along the `x` axis.
+    pub fn flow_right(mut self) -> Self {
+        self.direction = Direction::Horizontal;
+        self
+    }
+
+    /// Lay the `List`'s items out top-to-bottom and scroll along the `y` axis.
+    ///
+    /// This is the default `List` behaviour.
+    pub fn flow_down(mut self) -> Self {
+        self.direction = Direction::Vertical;
+        self
+    }
+
     /// Specifies that the `List` should be scrollable and should provide a `Scrollbar` to the
-    /// right of the items.
+    /// far side of the items (the right for a vertical `List`, the bottom for a horizontal one).
     pub fn scrollbar_next_to(mut self) -> Self {
         self.style.scrollbar_position = Some(Some(ScrollbarPosition::NextTo));
-        self.scroll_kids_vertically()
+        match self.direction {
+            Direction::Vertical => self.scroll_kids_vertically(),
+            Direction::Horizontal => self.scroll_kids_horizontally(),
+        }
     }
 
-    /// Specifies that the `List` should be scrollable and should provide a `Scrollbar` that hovers
-    /// above the right edge of the items and automatically hides when the user is not scrolling.
+    /// Specifies that the `List` should be scrollable and should provide a `Scrollbar` that
+    /// hovers above the far edge of the items and automatically hides when the user is not
+    /// scrolling.
     pub fn scrollbar_on_top(mut self) -> Self {
         self.style.scrollbar_position = Some(Some(ScrollbarPosition::OnTop));
-        self.scroll_kids_vertically()
+        match self.direction {
+            Direction::Vertical => self.scroll_kids_vertically(),
+            Direction::Horizontal => self.scroll_kids_horizontally(),
+        }
     }
 
     /// The width of the `Scrollbar`.
@@ -150,6 +348,31 @@ impl List {
         self
     }
 
+    /// The gap between the items and the `Scrollbar`'s track.
+    ///
+    /// Useful in combination with `scrollbar_on_top` to produce an embedded scrollbar that floats
+    /// with a margin rather than sitting flush against the edge of the items.
+    pub fn scrollbar_offset(mut self, offset: Scalar) -> Self {
+        self.style.scrollbar_offset = Some(offset);
+        self
+    }
+
+    /// Moves the `Scrollbar` to the near edge of the items (the left for a vertical `List`, the
+    /// top for a horizontal one) instead of the far edge.
+    ///
+    /// Should be called after `scrollbar_next_to` or `scrollbar_on_top`, whichever was used to
+    /// enable scrolling in the first place.
+    pub fn scrollbar_left(mut self) -> Self {
+        let position = self.style.scrollbar_position.clone().and_then(|p| p);
+        let left_position = match position {
+            Some(ScrollbarPosition::OnTop) | Some(ScrollbarPosition::OnTopLeft) =>
+                ScrollbarPosition::OnTopLeft,
+            _ => ScrollbarPosition::NextToLeft,
+        };
+        self.style.scrollbar_position = Some(Some(left_position));
+        self
+    }
+
     /// Indicates that an `Item` should be instatiated for every element in the list, regardless of
     /// whether or not the `Item` would be visible.
     ///
@@ -171,14 +394,143 @@ impl List {
         self
     }
 
+    /// Sets the currently selected item, overriding whatever the `List` last had selected.
+    ///
+    /// Pass the value read back from the `List`'s `Event` to keep the `List` and the
+    /// application's own model of the selection in sync. `None` is no override: the `List` keeps
+    /// whatever it last had selected, so the selection cannot be cleared through this method.
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Whether or not clicking an `Item` should select it.
+    ///
+    /// `false` by default, in which case the selection can only be changed via keyboard
+    /// navigation or by calling `.selected` directly.
+    pub fn select_on_click(mut self, select_on_click: bool) -> Self {
+        self.select_on_click = select_on_click;
+        self
+    }
+
+}
+
+
+impl ItemSizes {
+
+    /// The size of the item at the given index, along the `List`'s primary axis.
+    fn item_size(&self, i: usize) -> Scalar {
+        match *self {
+            ItemSizes::Uniform(size) => size,
+            ItemSizes::PerItem(ref f) => f(i),
+            ItemSizes::Measured { estimate, ref measured } => {
+                measured.borrow().get(i).cloned().unwrap_or(None).unwrap_or(estimate)
+            },
+        }
+    }
+
+    /// Build the cumulative-offset array `offsets` where `offsets[i]` is the distance between the
+    /// leading edge of the list and the leading edge of item `i`, and `offsets[num_items]` is the
+    /// total size of all items combined.
+    ///
+    /// An item with a size of `0.0` (or less) simply contributes nothing to the cumulative
+    /// offsets; `visible_idx_range`'s forward walk is bounded by `num_items` regardless, so it
+    /// never relies on the offsets being strictly increasing.
+    fn offsets(&self, num_items: u32) -> Vec<Scalar> {
+        let mut offsets = Vec::with_capacity(num_items as usize + 1);
+        let mut edge = 0.0;
+        offsets.push(edge);
+        for i in 0..num_items as usize {
+            let size = self.item_size(i).max(0.0);
+            edge += size;
+            offsets.push(edge);
+        }
+        offsets
+    }
+
+}
+
+
+/// Find the index of the first item, out of those described by `offsets`, whose trailing edge
+/// (`offsets[i + 1]`) exceeds `hidden_length`.
+///
+/// `offsets` must be the full cumulative-offset array as produced by `ItemSizes::offsets` (i.e.
+/// `offsets[1..]` sorted ascending). The binary search is performed over that trailing-edge slice
+/// directly, giving the first visible item's index without needing to scan from the start.
+fn first_visible_item_idx(offsets: &[Scalar], hidden_length: Scalar) -> usize {
+    let num_items = offsets.len() - 1;
+    let trailing_edges = &offsets[1..];
+    // Never report `Equal`, so that the search always lands on the partition point between the
+    // edges `<= hidden_length` and those beyond it, even where zero-size items repeat an edge.
+    let i = match trailing_edges.binary_search_by(|edge| {
+        if *edge <= hidden_length { std::cmp::Ordering::Less } else { std::cmp::Ordering::Greater }
+    }) {
+        Ok(i) | Err(i) => i,
+    };
+    std::cmp::min(i, num_items)
 }
 
+/// Determine the range of item indices that are visible given the cumulative `offsets`, the
+/// `hidden_length` (the distance scrolled past the leading edge) and the `visible_length` (the
+/// length of the visible window along the primary axis).
+fn visible_idx_range(
+    offsets: &[Scalar],
+    hidden_length: Scalar,
+    visible_length: Scalar,
+) -> std::ops::Range<usize> {
+    let num_items = offsets.len() - 1;
+    let first = first_visible_item_idx(offsets, hidden_length);
+
+    // Walk forward from the first visible item, accumulating sizes until the running leading
+    // edge reaches the trailing edge of the visible range.
+    let visible_trailing_edge = hidden_length + visible_length;
+    let mut end = first;
+    while end < num_items && offsets[end] < visible_trailing_edge {
+        end += 1;
+    }
+
+    first..end
+}
+
+/// Apply a single keyboard navigation `key` to `current`, clamping the result to
+/// `0..num_items`. Returns `current` unchanged if `key` is not a navigation key, and `None` if
+/// `num_items` is `0`.
+fn navigate_selection(current: Option<usize>, key: Key, num_items: usize) -> Option<usize> {
+    if num_items == 0 {
+        return None;
+    }
+    let last_idx = num_items - 1;
+    let delta: i64 = match key {
+        Key::Up | Key::Left => -1,
+        Key::Down | Key::Right => 1,
+        Key::PageUp => -10,
+        Key::PageDown => 10,
+        _ => 0,
+    };
+    match key {
+        Key::Home => Some(0),
+        Key::End => Some(last_idx),
+        // With nothing currently selected, a forward key should land on the first item and a
+        // backward key on the last, rather than biasing off index `0`.
+        _ if delta != 0 && current.is_none() => {
+            Some(if delta > 0 { 0 } else { last_idx })
+        },
+        _ if delta != 0 => {
+            let pos = current.unwrap_or(0) as i64;
+            Some(std::cmp::min(std::cmp::max(pos + delta, 0) as usize, last_idx))
+        },
+        _ => current,
+    }
+}
 
 
 impl Widget for List {
     type State = State;
     type Style = Style;
-    type Event = (Items, Option<Scrollbar>);
+    /// Yields the `Item`s to be instantiated, the `Scrollbar` if the `List` is scrollable, and
+    /// the newly selected index if the selection changed as a result of this update (e.g. via
+    /// keyboard navigation or a click on an item).
+    type Event = (Items, Option<Scrollbar>, Option<usize>);
 
     fn common(&self) -> &widget::CommonBuilder {
         &self.common
@@ -193,6 +545,8 @@ impl Widget for List {
             scroll_trigger_idx: widget::IndexSlot::new(),
             scrollbar_idx: widget::IndexSlot::new(),
             item_indices: Vec::new(),
+            selected: None,
+            measured: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -202,17 +556,32 @@ impl Widget for List {
 
     fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
         let widget::UpdateArgs { idx, state, rect, prev, mut ui, style, .. } = args;
-        let List { item_h, num_items, item_instantiation, .. } = self;
+        let List { item_sizes, num_items, item_instantiation, direction, selected, select_on_click, .. } = self;
 
-        // We need a positive item height in order to do anything useful.
-        assert!(item_h > 0.0, "the given item height was {:?} however it must be > 0", item_h);
+        // If the `List` is in `measured` mode, swap the freshly-constructed placeholder cache for
+        // the persistent one living in `State`, resizing it to match `num_items` along the way.
+        let item_sizes = match item_sizes {
+            ItemSizes::Measured { estimate, .. } => {
+                {
+                    let mut measured = state.measured.borrow_mut();
+                    measured.resize(num_items as usize, None);
+                }
+                ItemSizes::Measured { estimate: estimate, measured: state.measured.clone() }
+            },
+            other => other,
+        };
 
         // Determine whther or not the list is scrollable.
-        let is_scrollable = prev.maybe_y_scroll_state.as_ref()
-            .map(|scroll_state| scroll_state.offset_bounds.magnitude().is_sign_negative())
-            .unwrap_or(false);
+        let is_scrollable = match direction {
+            Direction::Vertical => prev.maybe_y_scroll_state.as_ref()
+                .map(|scroll_state| scroll_state.offset_bounds.magnitude().is_sign_negative())
+                .unwrap_or(false),
+            Direction::Horizontal => prev.maybe_x_scroll_state.as_ref()
+                .map(|scroll_state| scroll_state.offset_bounds.magnitude().is_sign_negative())
+                .unwrap_or(false),
+        };
 
-        // The width of the scrollbar.
+        // The thickness of the scrollbar, taken out of the cross-axis length of the items.
         let scrollbar_w = style.scrollbar_width(&ui.theme)
             .unwrap_or_else(|| {
                 ui.theme.widget_style::<widget::scrollbar::Style>()
@@ -221,23 +590,35 @@ impl Widget for List {
             });
 
         let scrollbar_position = style.scrollbar_position(&ui.theme);
-        let item_w = match (is_scrollable, scrollbar_position) {
-            (true, Some(ScrollbarPosition::NextTo)) => rect.w() - scrollbar_w,
-            _ => rect.w(),
+        let scrollbar_offset = style.scrollbar_offset(&ui.theme);
+        let cross_axis_len = match (is_scrollable, scrollbar_position) {
+            (true, Some(position)) if !position.auto_hide() =>
+                direction.cross_len(&rect) - scrollbar_w - scrollbar_offset,
+            _ => direction.cross_len(&rect),
         };
 
-        let total_item_h = num_items as Scalar * item_h;
+        // The cumulative offset of each item's leading edge, plus the total size of all items as
+        // the final entry.
+        let offsets = item_sizes.offsets(num_items);
+        let total_item_len = *offsets.last().unwrap_or(&0.0);
 
         // The widget used to scroll the `List`'s range.
         //
         // By using one long `Rectangle` widget to trigger the scrolling, this allows us to only
         // instantiate the visible items.
         let scroll_trigger_idx = state.scroll_trigger_idx.get(&mut ui);
-        widget::Rectangle::fill([rect.w(), total_item_h])
-            .mid_top_of(idx)
+        let scroll_trigger_dim = match direction {
+            Direction::Vertical => [rect.w(), total_item_len],
+            Direction::Horizontal => [total_item_len, rect.h()],
+        };
+        let scroll_trigger = widget::Rectangle::fill(scroll_trigger_dim)
             .color(color::TRANSPARENT)
-            .parent(idx)
-            .set(scroll_trigger_idx, &mut ui);
+            .parent(idx);
+        let scroll_trigger = match direction {
+            Direction::Vertical => scroll_trigger.mid_top_of(idx),
+            Direction::Horizontal => scroll_trigger.mid_left_of(idx),
+        };
+        scroll_trigger.set(scroll_trigger_idx, &mut ui);
 
         // Determine the index range of the items that should be instantiated.
         let (item_idx_range, first_item_margin) = match item_instantiation {
@@ -248,16 +629,11 @@ impl Widget for List {
             },
             ItemInstantiation::OnlyVisible => {
                 let scroll_trigger_rect = ui.rect_of(scroll_trigger_idx).unwrap();
-                let hidden_range_length = scroll_trigger_rect.top() - rect.top();
-                let num_top_hidden_items = hidden_range_length / item_h;
-                let num_visible_items = (rect.h() / item_h + 1.0).floor() as usize;
-
-                let first_visible_item_idx = num_top_hidden_items.floor() as usize;
-                let first_visible_item_margin = first_visible_item_idx as Scalar * item_h;
-                let end_of_visible_idx_range =
-                    std::cmp::min(first_visible_item_idx + num_visible_items, num_items as usize);
-                let range = first_visible_item_idx..end_of_visible_idx_range;
-                (range, first_visible_item_margin)
+                let hidden_length = direction.hidden_len(&scroll_trigger_rect, &rect);
+                let visible_length = direction.primary_len(&rect);
+                let range = visible_idx_range(&offsets, hidden_length, visible_length);
+                let first_item_margin = offsets[range.start];
+                (range, first_item_margin)
             },
         };
 
@@ -271,36 +647,147 @@ impl Widget for List {
             });
         }
 
+        // The selection as it stood before this update, used to detect whether it changes as a
+        // result of this update (as opposed to comparing against the builder's `selected`
+        // override, which is `None` unless the caller feeds the `Event`'s selection straight
+        // back in).
+        let previous = state.selected;
+
+        // The selection the `List` should use for this update, preferring an explicit override
+        // from the caller over whatever was last selected internally. With no items there is
+        // nothing to select.
+        let starting_selected = if num_items == 0 {
+            None
+        } else {
+            selected.or(previous).map(|i| std::cmp::min(i, num_items as usize - 1))
+        };
+
+        // Keyboard navigation is captured whenever the `List` itself or one of its previously
+        // instantiated `Item`s has focus.
+        let mut new_selected = starting_selected;
+        if num_items > 0 {
+            for press in ui.widget_input(idx).presses().key() {
+                new_selected = navigate_selection(new_selected, press.key, num_items as usize);
+            }
+            for &item_idx in &state.item_indices {
+                for press in ui.widget_input(item_idx).presses().key() {
+                    new_selected = navigate_selection(new_selected, press.key, num_items as usize);
+                }
+            }
+
+            // Clicking an `Item` selects it, if enabled.
+            if select_on_click {
+                for (i, &item_idx) in state.item_indices.iter().enumerate() {
+                    if ui.widget_input(item_idx).clicks().left().next().is_some() {
+                        if let Some(selected_item) = item_idx_range.clone().nth(i) {
+                            new_selected = Some(selected_item);
+                        }
+                    }
+                }
+            }
+        }
+
+        if new_selected != previous {
+            state.update(|state| state.selected = new_selected);
+        }
+
+        let selected_changed = if new_selected != previous { new_selected } else { None };
+
+        // Only scroll the selection into view when it actually changed this update (e.g. via
+        // keyboard navigation or a click). Otherwise an item sitting outside the visible range
+        // (simply because the user manually scrolled away from it) would be yanked back into
+        // view every single frame.
+        if num_items > 0 {
+            if let Some(i) = selected_changed {
+                let selected_top = offsets[i];
+                let selected_bottom = offsets[i + 1];
+                let visible_top = direction.leading_edge(&rect);
+                let visible_bottom = visible_top + direction.primary_len(&rect);
+                let scroll_trigger_rect = ui.rect_of(scroll_trigger_idx).unwrap();
+                let hidden_length = direction.hidden_len(&scroll_trigger_rect, &rect);
+                if selected_top < hidden_length {
+                    let delta = selected_top - hidden_length;
+                    match direction {
+                        Direction::Vertical => ui.scroll_widget(idx, [0.0, delta]),
+                        Direction::Horizontal => ui.scroll_widget(idx, [-delta, 0.0]),
+                    }
+                } else if selected_bottom > hidden_length + (visible_bottom - visible_top) {
+                    let delta = selected_bottom - (hidden_length + (visible_bottom - visible_top));
+                    match direction {
+                        Direction::Vertical => ui.scroll_widget(idx, [0.0, delta]),
+                        Direction::Horizontal => ui.scroll_widget(idx, [-delta, 0.0]),
+                    }
+                }
+            }
+        }
+
+        let measured = match item_sizes {
+            ItemSizes::Measured { ref measured, .. } => Some(measured.clone()),
+            _ => None,
+        };
+
         let items = Items {
+            selected: new_selected,
             list_idx: idx,
             item_indices: item_idx_range,
             next_item_indices_index: 0,
             last_idx: None,
             scroll_trigger_idx: scroll_trigger_idx,
             first_item_margin: first_item_margin,
-            item_w: item_w,
-            item_h: item_h,
+            cross_axis_len: cross_axis_len,
+            item_sizes: item_sizes,
+            direction: direction,
+            measured: measured,
         };
 
         // Instantiate the `Scrollbar` if necessary.
-        let auto_hide = match scrollbar_position {
-            Some(ScrollbarPosition::NextTo) => false,
-            Some(ScrollbarPosition::OnTop) => true,
-            None => return (items, None),
+        let position = match scrollbar_position {
+            Some(position) => position,
+            None => return (items, None, selected_changed),
         };
+        let auto_hide = position.auto_hide();
+        let is_left = position.is_left();
+        // In `auto_hide` (embedded) mode the track floats `scrollbar_offset` away from the edge
+        // of the items rather than sitting flush against it.
+        let inset = if auto_hide { scrollbar_offset } else { 0.0 };
         let scrollbar_color = style.scrollbar_color(&ui.theme);
         let scrollbar_idx = state.scrollbar_idx.get(&mut ui);
-        let scrollbar = widget::Scrollbar::y_axis(idx)
-            .and_if(prev.maybe_floating.is_some(), |s| s.floating(true))
-            .color(scrollbar_color)
-            .thickness(scrollbar_w)
-            .auto_hide(auto_hide);
+        let scrollbar_widget = match direction {
+            Direction::Vertical => {
+                let scrollbar = widget::Scrollbar::y_axis(idx)
+                    .and_if(prev.maybe_floating.is_some(), |s| s.floating(true))
+                    .and_if(is_left, |s| s.align_left_of(idx))
+                    .color(scrollbar_color)
+                    .thickness(scrollbar_w)
+                    .auto_hide(auto_hide);
+                let scrollbar = if is_left {
+                    scrollbar.x_relative(inset)
+                } else {
+                    scrollbar.x_relative(-inset)
+                };
+                ScrollbarWidget::Y(scrollbar)
+            },
+            Direction::Horizontal => {
+                let scrollbar = widget::Scrollbar::x_axis(idx)
+                    .and_if(prev.maybe_floating.is_some(), |s| s.floating(true))
+                    .and_if(is_left, |s| s.align_top_of(idx))
+                    .color(scrollbar_color)
+                    .thickness(scrollbar_w)
+                    .auto_hide(auto_hide);
+                let scrollbar = if is_left {
+                    scrollbar.y_relative(-inset)
+                } else {
+                    scrollbar.y_relative(inset)
+                };
+                ScrollbarWidget::X(scrollbar)
+            },
+        };
         let scrollbar = Scrollbar {
-            widget: scrollbar,
+            widget: scrollbar_widget,
             idx: scrollbar_idx,
         };
 
-        (items, Some(scrollbar))
+        (items, Some(scrollbar), selected_changed)
     }
 }
 
@@ -316,8 +803,11 @@ impl Items {
             list_idx,
             scroll_trigger_idx,
             first_item_margin,
-            item_w,
-            item_h,
+            cross_axis_len,
+            ref item_sizes,
+            direction,
+            selected,
+            ref measured,
         } = *self;
 
         // Retrieve the `node_index` that was generated for the next `Item`.
@@ -336,13 +826,21 @@ impl Items {
 
         match (item_indices.next(), node_index) {
             (Some(i), Some(node_index)) => {
+                let primary_len = item_sizes.item_size(i);
+                let (w, h) = match direction {
+                    Direction::Vertical => (cross_axis_len, primary_len),
+                    Direction::Horizontal => (primary_len, cross_axis_len),
+                };
                 let item = Item {
                     i: i,
                     last_idx: *last_idx,
                     widget_idx: node_index,
+                    direction: direction,
+                    measured: measured.clone(),
                     scroll_trigger_idx: scroll_trigger_idx,
-                    w: item_w,
-                    h: item_h,
+                    w: w,
+                    h: h,
+                    is_selected: selected == Some(i),
                     first_item_margin: first_item_margin,
                 };
                 *last_idx = Some(node_index);
@@ -364,20 +862,66 @@ impl Item {
     /// - dimensions of the widget.
     /// - parent of the widget.
     /// - and finally sets the widget within the `Ui`.
+    ///
+    /// If the `List` is in `measured` mode, this also reads the widget's actual size back out of
+    /// the `Ui` and caches it for use on subsequent frames.
     pub fn set<W>(self, widget: W, ui: &mut UiCell) -> W::Event
         where W: Widget,
     {
-        let Item { widget_idx, last_idx, w, h, scroll_trigger_idx, first_item_margin, .. } = self;
+        let Item {
+            i,
+            widget_idx,
+            last_idx,
+            w,
+            h,
+            direction,
+            measured,
+            scroll_trigger_idx,
+            first_item_margin,
+            ..
+        } = self;
+
+        // In `measured` mode the primary-axis dimension is only an estimate, so it must be left
+        // for the widget to size itself; only the cross-axis is pinned. Otherwise (`w`, `h`) are
+        // both known up-front and the widget is force-sized to them.
+        let widget = match (direction, measured.is_some()) {
+            (_, false) => widget.w_h(w, h),
+            (Direction::Vertical, true) => widget.w(w),
+            (Direction::Horizontal, true) => widget.h(h),
+        };
 
-        widget
-            .w_h(w, h)
-            .and(|w| match last_idx {
-                None => w.mid_top_with_margin_on(scroll_trigger_idx, first_item_margin)
+        let event = widget
+            .and(|w| match (last_idx, direction) {
+                (None, Direction::Vertical) => w
+                    .mid_top_with_margin_on(scroll_trigger_idx, first_item_margin)
                     .align_left_of(scroll_trigger_idx),
-                Some(idx) => w.down_from(idx, 0.0),
+                (None, Direction::Horizontal) => w
+                    .mid_left_with_margin_on(scroll_trigger_idx, first_item_margin)
+                    .align_top_of(scroll_trigger_idx),
+                (Some(idx), Direction::Vertical) => w.down_from(idx, 0.0),
+                (Some(idx), Direction::Horizontal) => w.right_from(idx, 0.0),
             })
             .parent(scroll_trigger_idx)
-            .set(widget_idx, ui)
+            .set(widget_idx, ui);
+
+        // In `measured` mode, read the widget's actual size back out of the `Ui` and cache it,
+        // requesting a redraw if it differs from the estimate (or earlier measurement) the item was
+        // laid out with, so that the cumulative offsets converge to the true positions.
+        if let Some(measured) = measured {
+            let (actual, laid_out) = match direction {
+                Direction::Vertical => (ui.h_of(widget_idx).unwrap_or(h), h),
+                Direction::Horizontal => (ui.w_of(widget_idx).unwrap_or(w), w),
+            };
+            let mut measured = measured.borrow_mut();
+            if i < measured.len() {
+                measured[i] = Some(actual);
+            }
+            if actual != laid_out {
+                ui.needs_redraw();
+            }
+        }
+
+        event
     }
 
 }
@@ -387,6 +931,94 @@ impl Scrollbar {
     /// Set the `Scrollbar` within the given `Ui`.
     pub fn set(self, ui: &mut UiCell) {
         let Scrollbar { widget, idx } = self;
-        widget.set(idx, ui);
+        match widget {
+            ScrollbarWidget::X(widget) => widget.set(idx, ui),
+            ScrollbarWidget::Y(widget) => widget.set(idx, ui),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        first_visible_item_idx,
+        navigate_selection,
+        visible_idx_range,
+        ItemSizes,
+        Key,
+    };
+
+    #[test]
+    fn offsets_uniform() {
+        let sizes = ItemSizes::Uniform(10.0);
+        assert_eq!(sizes.offsets(4), vec![0.0, 10.0, 20.0, 30.0, 40.0]);
+    }
+
+    #[test]
+    fn offsets_per_item() {
+        let heights = vec![10.0, 20.0, 5.0];
+        let sizes = ItemSizes::PerItem(::std::rc::Rc::new(move |i| heights[i]));
+        assert_eq!(sizes.offsets(3), vec![0.0, 10.0, 30.0, 35.0]);
+    }
+
+    #[test]
+    fn offsets_clamps_zero_and_negative_sizes() {
+        let heights = vec![10.0, 0.0, -5.0, 10.0];
+        let sizes = ItemSizes::PerItem(::std::rc::Rc::new(move |i| heights[i]));
+        // A zero or negative item height simply contributes nothing to the cumulative offsets,
+        // rather than going negative or panicking.
+        assert_eq!(sizes.offsets(4), vec![0.0, 10.0, 10.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn first_visible_item_idx_at_exact_boundaries() {
+        let offsets = vec![0.0, 10.0, 20.0, 30.0, 40.0];
+        assert_eq!(first_visible_item_idx(&offsets, 0.0), 0);
+        assert_eq!(first_visible_item_idx(&offsets, 9.9), 0);
+        assert_eq!(first_visible_item_idx(&offsets, 10.0), 1);
+        assert_eq!(first_visible_item_idx(&offsets, 25.0), 2);
+        // Scrolled past the end of the list: clamp to `num_items`.
+        assert_eq!(first_visible_item_idx(&offsets, 1000.0), 4);
+    }
+
+    #[test]
+    fn visible_idx_range_covers_the_viewport() {
+        let offsets = vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0];
+        // Scrolled down by 15 with a viewport of 22: items 1 (10-20), 2 (20-30) and 3 (30-40)
+        // overlap the visible window from 15 to 37.
+        assert_eq!(visible_idx_range(&offsets, 15.0, 22.0), 1..4);
+        // An empty list has no visible items.
+        assert_eq!(visible_idx_range(&[0.0], 0.0, 100.0), 0..0);
+    }
+
+    #[test]
+    fn navigate_selection_clamps_to_bounds() {
+        assert_eq!(navigate_selection(Some(0), Key::Up, 5), Some(0));
+        assert_eq!(navigate_selection(Some(4), Key::Down, 5), Some(4));
+        assert_eq!(navigate_selection(Some(2), Key::Down, 5), Some(3));
+        assert_eq!(navigate_selection(Some(2), Key::Up, 5), Some(1));
+    }
+
+    #[test]
+    fn navigate_selection_home_end_and_paging() {
+        assert_eq!(navigate_selection(Some(3), Key::Home, 10), Some(0));
+        assert_eq!(navigate_selection(Some(3), Key::End, 10), Some(9));
+        assert_eq!(navigate_selection(Some(5), Key::PageDown, 10), Some(9));
+        assert_eq!(navigate_selection(Some(5), Key::PageUp, 10), Some(0));
+    }
+
+    #[test]
+    fn navigate_selection_from_none_lands_on_the_first_or_last_item() {
+        assert_eq!(navigate_selection(None, Key::Down, 10), Some(0));
+        assert_eq!(navigate_selection(None, Key::Right, 10), Some(0));
+        assert_eq!(navigate_selection(None, Key::Up, 10), Some(9));
+        assert_eq!(navigate_selection(None, Key::Left, 10), Some(9));
+    }
+
+    #[test]
+    fn navigate_selection_on_empty_list() {
+        assert_eq!(navigate_selection(Some(0), Key::Down, 0), None);
+        assert_eq!(navigate_selection(None, Key::Home, 0), None);
     }
 }